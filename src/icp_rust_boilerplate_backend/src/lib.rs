@@ -7,6 +7,8 @@ use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemor
 use ic_stable_structures::{BoundedStorable, Cell, DefaultMemoryImpl, StableBTreeMap, Storable};
 use std::{borrow::Cow, cell::RefCell};
 use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::ops::Bound;
 
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 type IdCell = Cell<u64, Memory>;
@@ -50,6 +52,144 @@ impl BoundedStorable for House {
     const IS_FIXED_SIZE: bool = false;
 }
 
+// Keyed by (term, house_id) rather than one growing posting list per term,
+// so a popular term (e.g. a common house_type) never forces a single
+// stable-memory value past its bound as more houses share it.
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct PostingKey {
+    term: String,
+    house_id: u64,
+}
+
+impl Storable for PostingKey {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for PostingKey {
+    // `term` is a single token drawn from owners_name/house_type/location, each
+    // bounded only by House::MAX_SIZE as a whole, so a field with no whitespace
+    // can produce a token nearly that long. Derive the bound from House's
+    // rather than picking one independently.
+    const MAX_SIZE: u32 = House::MAX_SIZE + 16;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct PostingEntry {
+    house_id: u64,
+    owners_name_tf: u32,
+    house_type_tf: u32,
+    location_tf: u32,
+}
+
+impl Storable for PostingEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for PostingEntry {
+    const MAX_SIZE: u32 = 64;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+const OWNERS_NAME_WEIGHT: f64 = 3.0;
+const HOUSE_TYPE_WEIGHT: f64 = 2.0;
+const LOCATION_WEIGHT: f64 = 1.0;
+
+// Every CHECKPOINT_INTERVAL-th change record for a house carries a full
+// `House` snapshot so `get_house_at` never has to replay more than that many
+// diffs to reconstruct a historical state.
+const CHECKPOINT_INTERVAL: u64 = 64;
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct ChangeLogKey {
+    house_id: u64,
+    sequence: u64,
+}
+
+impl Storable for ChangeLogKey {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for ChangeLogKey {
+    const MAX_SIZE: u32 = 64;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Kept in sync with STORAGE_HOUSE so house listings can be paged in true
+// alphabetical order: keys sort by (owners_name, house_id), so a range scan
+// starting just past the last-seen entry resumes at the right point even as
+// houses with the same name are inserted or removed.
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct NameIndexKey {
+    owners_name: String,
+    house_id: u64,
+}
+
+impl Storable for NameIndexKey {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for NameIndexKey {
+    // owners_name is bounded only by House::MAX_SIZE as a whole, so derive
+    // this bound from House's rather than picking one independently.
+    const MAX_SIZE: u32 = House::MAX_SIZE + 16;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct ChangeRecord {
+    sequence: u64,
+    timestamp: u64,
+    change_type: String,
+    field: String,
+    old_value: String,
+    new_value: String,
+    snapshot: Option<House>,
+}
+
+impl Storable for ChangeRecord {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for ChangeRecord {
+    // old_value/new_value can each hold up to a full field's worth of text
+    // (bounded by House::MAX_SIZE), and a checkpoint record additionally
+    // embeds a full House snapshot, so the bound is derived from House's
+    // rather than picked independently.
+    const MAX_SIZE: u32 = House::MAX_SIZE * 3 + 256;
+    const IS_FIXED_SIZE: bool = false;
+}
+
 thread_local! {
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
         MemoryManager::init(DefaultMemoryImpl::default())
@@ -64,6 +204,21 @@ thread_local! {
         RefCell::new(StableBTreeMap::init(
             MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(1)))
         ));
+
+    static SEARCH_INDEX: RefCell<StableBTreeMap<PostingKey, PostingEntry, Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(2)))
+        ));
+
+    static CHANGE_LOG: RefCell<StableBTreeMap<ChangeLogKey, ChangeRecord, Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3)))
+        ));
+
+    static NAME_INDEX: RefCell<StableBTreeMap<NameIndexKey, u64, Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4)))
+        ));
 }
 
 #[ic_cdk::query]
@@ -101,26 +256,368 @@ fn get_available_houses() -> Vec<House> {
 
 #[ic_cdk::query]
 fn search_houses(query: String) -> Vec<House> {
+    let query_tokens = tokenize(&query);
+    if query_tokens.is_empty() {
+        return Vec::new();
+    }
+
+    let mut field_scores: HashMap<u64, f64> = HashMap::new();
+    let mut tokens_matched: HashMap<u64, HashSet<usize>> = HashMap::new();
+
+    for (token_idx, token) in query_tokens.iter().enumerate() {
+        for entry in matching_terms(token) {
+            let score = entry.owners_name_tf as f64 * OWNERS_NAME_WEIGHT
+                + entry.house_type_tf as f64 * HOUSE_TYPE_WEIGHT
+                + entry.location_tf as f64 * LOCATION_WEIGHT;
+            *field_scores.entry(entry.house_id).or_insert(0.0) += score;
+            tokens_matched.entry(entry.house_id).or_default().insert(token_idx);
+        }
+    }
+
+    let mut ranked: Vec<(u64, f64)> = field_scores
+        .into_iter()
+        .map(|(house_id, score)| {
+            let matched = tokens_matched.get(&house_id).map(|s| s.len()).unwrap_or(0) as f64;
+            (house_id, score * matched)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    ranked
+        .into_iter()
+        .filter_map(|(house_id, _)| _get_house(&house_id))
+        .collect()
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+        .collect()
+}
+
+fn term_frequencies(tokens: &[String]) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    for token in tokens {
+        *counts.entry(token.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn house_terms(house: &House) -> (HashMap<String, u32>, HashMap<String, u32>, HashMap<String, u32>) {
+    (
+        term_frequencies(&tokenize(&house.owners_name)),
+        term_frequencies(&tokenize(&house.house_type)),
+        term_frequencies(&tokenize(&house.location)),
+    )
+}
+
+fn index_house(house: &House) {
+    let (owners_name_tf, house_type_tf, location_tf) = house_terms(house);
+    let mut terms: HashSet<String> = HashSet::new();
+    terms.extend(owners_name_tf.keys().cloned());
+    terms.extend(house_type_tf.keys().cloned());
+    terms.extend(location_tf.keys().cloned());
+
+    SEARCH_INDEX.with(|index| {
+        let mut index = index.borrow_mut();
+        for term in terms {
+            let entry = PostingEntry {
+                house_id: house.id,
+                owners_name_tf: *owners_name_tf.get(&term).unwrap_or(&0),
+                house_type_tf: *house_type_tf.get(&term).unwrap_or(&0),
+                location_tf: *location_tf.get(&term).unwrap_or(&0),
+            };
+            index.insert(PostingKey { term, house_id: house.id }, entry);
+        }
+    });
+}
+
+fn deindex_house(house: &House) {
+    let (owners_name_tf, house_type_tf, location_tf) = house_terms(house);
+    let mut terms: HashSet<String> = HashSet::new();
+    terms.extend(owners_name_tf.keys().cloned());
+    terms.extend(house_type_tf.keys().cloned());
+    terms.extend(location_tf.keys().cloned());
+
+    SEARCH_INDEX.with(|index| {
+        let mut index = index.borrow_mut();
+        for term in terms {
+            index.remove(&PostingKey { term, house_id: house.id });
+        }
+    });
+}
+
+// Candidates are restricted to postings whose term shares the query token's
+// first character, scanned via `PostingKey`'s (term, house_id) ordering, so
+// a query never has to walk the whole index. The upper bound is the next
+// Unicode scalar value after that character (falling back to unbounded when
+// the character is already `char::MAX`), not a byte-wise bump, since the
+// first character can be any codepoint a free-text owner/location can hold.
+fn matching_terms(query_token: &str) -> Vec<PostingEntry> {
+    let max_distance = if query_token.chars().count() > 8 { 2 } else { 1 };
+    let prefix_char = match query_token.chars().next() {
+        Some(c) => c,
+        None => return Vec::new(),
+    };
+    let start = PostingKey { term: prefix_char.to_string(), house_id: 0 };
+    let end = char::from_u32(prefix_char as u32 + 1).map(|c| PostingKey { term: c.to_string(), house_id: 0 });
+
+    SEARCH_INDEX.with(|index| {
+        let index = index.borrow();
+        let candidates: Vec<(PostingKey, PostingEntry)> = match end {
+            Some(end) => index.range(start..end).collect(),
+            None => index.range(start..).collect(),
+        };
+        candidates
+            .into_iter()
+            .filter(|(key, _)| key.term == query_token || levenshtein(&key.term, query_token) <= max_distance)
+            .map(|(_, entry)| entry)
+            .collect()
+    })
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut prev = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let cur = row[j];
+            row[j] = if a[i - 1] == b[j - 1] {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(row[j - 1])
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+#[ic_cdk::query]
+fn search_price(query: u64) -> Vec<House> {
     STORAGE_HOUSE.with(|service| {
         service
             .borrow()
             .iter()
-            .filter(|(_, house)| house.owners_name.contains(&query) || house.house_type.contains(&query))
+            .filter(|(_, house)| house.price == query)
             .map(|(_, house)| house.clone())
             .collect()
     })
 }
 
+#[derive(candid::CandidType, Clone, Serialize, Deserialize)]
+enum SortField {
+    Price,
+    OwnersName,
+    CreatedAt,
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize)]
+enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct HouseFilter {
+    price_min: Option<u64>,
+    price_max: Option<u64>,
+    house_type: Option<String>,
+    location: Option<String>,
+    availability: Option<bool>,
+    min_available_units: Option<u64>,
+    sort_by: Option<SortField>,
+    sort_order: Option<SortOrder>,
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize)]
+enum Conversion {
+    Integer,
+    Float,
+    Boolean,
+    Bytes,
+    Timestamp,
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize)]
+enum TypedValue {
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Bytes(Vec<u8>),
+    Timestamp(u64),
+}
+
+fn parse_as(text: &str, conversion: &Conversion) -> Result<TypedValue, Error> {
+    match conversion {
+        Conversion::Integer => text
+            .parse::<i64>()
+            .map(TypedValue::Integer)
+            .map_err(|_| Error::InvalidFilter {
+                msg: format!("'{}' is not a valid integer", text),
+            }),
+        Conversion::Float => text
+            .parse::<f64>()
+            .map(TypedValue::Float)
+            .map_err(|_| Error::InvalidFilter {
+                msg: format!("'{}' is not a valid float", text),
+            }),
+        Conversion::Boolean => match text.to_lowercase().as_str() {
+            "true" => Ok(TypedValue::Boolean(true)),
+            "false" => Ok(TypedValue::Boolean(false)),
+            _ => Err(Error::InvalidFilter {
+                msg: format!("'{}' is not a valid boolean", text),
+            }),
+        },
+        Conversion::Bytes => Ok(TypedValue::Bytes(text.as_bytes().to_vec())),
+        Conversion::Timestamp => text
+            .parse::<u64>()
+            .map(TypedValue::Timestamp)
+            .map_err(|_| Error::InvalidFilter {
+                msg: format!("'{}' is not a valid timestamp", text),
+            }),
+    }
+}
+
+fn parse_u64_field(text: &str) -> Result<u64, Error> {
+    match parse_as(text, &Conversion::Integer)? {
+        TypedValue::Integer(value) if value >= 0 => Ok(value as u64),
+        _ => Err(Error::InvalidFilter {
+            msg: format!("'{}' is not a valid non-negative integer", text),
+        }),
+    }
+}
+
+fn parse_bool_field(text: &str) -> Result<bool, Error> {
+    match parse_as(text, &Conversion::Boolean)? {
+        TypedValue::Boolean(value) => Ok(value),
+        _ => unreachable!(),
+    }
+}
+
+fn parse_sort_field(text: &str) -> Result<SortField, Error> {
+    match text {
+        "Price" => Ok(SortField::Price),
+        "OwnersName" => Ok(SortField::OwnersName),
+        "CreatedAt" => Ok(SortField::CreatedAt),
+        _ => Err(Error::InvalidFilter {
+            msg: format!("'{}' is not a valid sort_by value", text),
+        }),
+    }
+}
+
+fn parse_sort_order(text: &str) -> Result<SortOrder, Error> {
+    match text {
+        "Ascending" => Ok(SortOrder::Ascending),
+        "Descending" => Ok(SortOrder::Descending),
+        _ => Err(Error::InvalidFilter {
+            msg: format!("'{}' is not a valid sort_order value", text),
+        }),
+    }
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct StringHouseFilter {
+    price_min: Option<String>,
+    price_max: Option<String>,
+    house_type: Option<String>,
+    location: Option<String>,
+    availability: Option<String>,
+    min_available_units: Option<String>,
+    sort_by: Option<String>,
+    sort_order: Option<String>,
+}
+
+fn parse_house_filter(raw: StringHouseFilter) -> Result<HouseFilter, Error> {
+    Ok(HouseFilter {
+        price_min: raw.price_min.as_deref().map(parse_u64_field).transpose()?,
+        price_max: raw.price_max.as_deref().map(parse_u64_field).transpose()?,
+        house_type: raw.house_type,
+        location: raw.location,
+        availability: raw.availability.as_deref().map(parse_bool_field).transpose()?,
+        min_available_units: raw
+            .min_available_units
+            .as_deref()
+            .map(parse_u64_field)
+            .transpose()?,
+        sort_by: raw.sort_by.as_deref().map(parse_sort_field).transpose()?,
+        sort_order: raw.sort_order.as_deref().map(parse_sort_order).transpose()?,
+    })
+}
+
+fn house_matches_filter(house: &House, filter: &HouseFilter) -> bool {
+    if let Some(min) = filter.price_min {
+        if house.price < min {
+            return false;
+        }
+    }
+    if let Some(max) = filter.price_max {
+        if house.price > max {
+            return false;
+        }
+    }
+    if let Some(house_type) = &filter.house_type {
+        if &house.house_type != house_type {
+            return false;
+        }
+    }
+    if let Some(location) = &filter.location {
+        if &house.location != location {
+            return false;
+        }
+    }
+    if let Some(availability) = filter.availability {
+        if house.availability != availability {
+            return false;
+        }
+    }
+    if let Some(min_units) = filter.min_available_units {
+        if house.availabile_units < min_units {
+            return false;
+        }
+    }
+    true
+}
+
+fn compare_houses(a: &House, b: &House, sort_by: &SortField) -> std::cmp::Ordering {
+    match sort_by {
+        SortField::Price => a.price.cmp(&b.price),
+        SortField::OwnersName => a.owners_name.cmp(&b.owners_name),
+        SortField::CreatedAt => a.created_at.cmp(&b.created_at),
+    }
+}
+
 #[ic_cdk::query]
-fn search_price(query: u64) -> Vec<House> {
-    STORAGE_HOUSE.with(|service| {
+fn query_houses(filter: HouseFilter) -> Vec<House> {
+    let mut matches: Vec<House> = STORAGE_HOUSE.with(|service| {
         service
             .borrow()
             .iter()
-            .filter(|(_, house)| house.price == query)
             .map(|(_, house)| house.clone())
+            .filter(|house| house_matches_filter(house, &filter))
             .collect()
-    })
+    });
+
+    if let Some(sort_by) = &filter.sort_by {
+        matches.sort_by(|a, b| compare_houses(a, b, sort_by));
+        if matches!(filter.sort_order, Some(SortOrder::Descending)) {
+            matches.reverse();
+        }
+    }
+
+    matches
+}
+
+#[ic_cdk::query]
+fn query_houses_raw(filter: StringHouseFilter) -> Result<Vec<House>, Error> {
+    let filter = parse_house_filter(filter)?;
+    Ok(query_houses(filter))
 }
 
 #[ic_cdk::update]
@@ -143,13 +640,15 @@ fn add_house(house: HousePayload) -> Option<House> {
         availability: house.availability,
     };
     do_insert_house(&storage_house);
+    append_change(id, "Created", "house", String::new(), "created".to_string(), &storage_house);
     Some(storage_house)
 }
 
 #[ic_cdk::update]
 fn update_house(id: u64, payload: HousePayload) -> Result<House, Error> {
     match STORAGE_HOUSE.with(|service| service.borrow_mut().get(&id)) {
-        Some(mut house) => {
+        Some(old_house) => {
+            let mut house = old_house.clone();
             house.owners_name = payload.owners_name;
             house.house_type = payload.house_type;
             house.location = payload.location;
@@ -158,6 +657,7 @@ fn update_house(id: u64, payload: HousePayload) -> Result<House, Error> {
             house.price = payload.price;
             house.availability = payload.availability;
             do_insert_house(&house);
+            log_field_changes(id, "Updated", &old_house, &house);
             Ok(house.clone())
         }
         None => Err(Error::NotFound {
@@ -172,15 +672,17 @@ fn update_house(id: u64, payload: HousePayload) -> Result<House, Error> {
 #[ic_cdk::update]
 fn buy_house(id: u64, payload: HousePayload) -> Result<House, Error> {
     match STORAGE_HOUSE.with(|service| service.borrow_mut().get(&id)) {
-        Some(mut house) => {
+        Some(old_house) => {
+            let mut house = old_house.clone();
             house.owners_name = payload.owners_name;
             house.house_type = payload.house_type;
             house.location = payload.location;
             house.updated_at = Some(time());
             house.availabile_units = payload.availabile_units - 1;
             house.price = payload.price;
-            house.availability = payload.availability; 
+            house.availability = payload.availability;
             do_insert_house(&house);
+            log_field_changes(id, "Bought", &old_house, &house);
             Ok(house.clone())
         }
         None => Err(Error::NotFound {
@@ -195,7 +697,12 @@ fn buy_house(id: u64, payload: HousePayload) -> Result<House, Error> {
 #[ic_cdk::update]
 fn delete_house(id: u64) -> Result<House, Error> {
     match STORAGE_HOUSE.with(|service| service.borrow_mut().remove(&id)) {
-        Some(house) => Ok(house),
+        Some(house) => {
+            deindex_house(&house);
+            NAME_INDEX.with(|index| index.borrow_mut().remove(&name_index_key(&house)));
+            append_change(id, "Deleted", "house", "present".to_string(), "deleted".to_string(), &house);
+            Ok(house)
+        }
         None => Err(Error::NotFound {
             msg: format!(
                 "couldn't delete a house with id={}. house not found.",
@@ -218,9 +725,11 @@ fn house_availability(id: u64) -> Result<bool, Error> {
 #[ic_cdk::update]
 fn set_house_available(id: u64) -> Result<House, Error> {
     match STORAGE_HOUSE.with(|service| service.borrow_mut().get(&id)) {
-        Some(mut house) => {
+        Some(old_house) => {
+            let mut house = old_house.clone();
             house.availability = true;
             do_insert_house(&house);
+            log_field_changes(id, "AvailabilitySet", &old_house, &house);
             Ok(house.clone())
         }
         None => Err(Error::NotFound {
@@ -231,9 +740,11 @@ fn set_house_available(id: u64) -> Result<House, Error> {
 
 #[ic_cdk::update]
 fn set_house_not_available(id: u64) -> Result<House, Error> {
-    if let Some(mut house) = STORAGE_HOUSE.with(|service| service.borrow_mut().get(&id)) {
+    if let Some(old_house) = STORAGE_HOUSE.with(|service| service.borrow_mut().get(&id)) {
+        let mut house = old_house.clone();
         house.availability = false;
         do_insert_house(&house);
+        log_field_changes(id, "AvailabilitySet", &old_house, &house);
         Ok(house.clone())
     } else {
         Err(Error::NotFound {
@@ -245,9 +756,11 @@ fn set_house_not_available(id: u64) -> Result<House, Error> {
 #[ic_cdk::update]
 fn set_price(id: u64, price: u64) -> Result<House, Error> {
     match STORAGE_HOUSE.with(|service| service.borrow_mut().get(&id)) {
-        Some(mut house) => {
+        Some(old_house) => {
+            let mut house = old_house.clone();
             house.price = price;
             do_insert_house(&house);
+            log_field_changes(id, "PriceChanged", &old_house, &house);
             Ok(house.clone())
         }
         None => Err(Error::NotFound {
@@ -257,12 +770,26 @@ fn set_price(id: u64, price: u64) -> Result<House, Error> {
 }
 
 fn do_insert_house(house: &House) {
+    if let Some(previous) = STORAGE_HOUSE.with(|service| service.borrow().get(&house.id)) {
+        deindex_house(&previous);
+        NAME_INDEX.with(|index| index.borrow_mut().remove(&name_index_key(&previous)));
+    }
+    index_house(house);
+    NAME_INDEX.with(|index| index.borrow_mut().insert(name_index_key(house), house.id));
     STORAGE_HOUSE.with(|service| service.borrow_mut().insert(house.id, house.clone()));
 }
 
+fn name_index_key(house: &House) -> NameIndexKey {
+    NameIndexKey {
+        owners_name: house.owners_name.clone(),
+        house_id: house.id,
+    }
+}
+
 #[derive(candid::CandidType, Deserialize, Serialize)]
 enum Error {
     NotFound { msg: String },
+    InvalidFilter { msg: String },
 }
 
 fn _get_house(id: &u64) -> Option<House> {
@@ -272,10 +799,129 @@ fn _get_house(id: &u64) -> Option<House> {
         .get(id)
 }
 
-#[derive(candid::CandidType, Serialize, Deserialize)]
-struct ChangeRecord {
-    timestamp: u64,
-    change_type: String,
+// Sequence 0 always lands on a checkpoint (0 % CHECKPOINT_INTERVAL == 0), so
+// the first record for a house is already a full snapshot.
+fn next_sequence(house_id: u64) -> u64 {
+    CHANGE_LOG.with(|log| {
+        log.borrow()
+            .range(
+                ChangeLogKey { house_id, sequence: 0 }..ChangeLogKey {
+                    house_id: house_id + 1,
+                    sequence: 0,
+                },
+            )
+            .map(|(key, _)| key.sequence)
+            .max()
+            .map(|sequence| sequence + 1)
+            .unwrap_or(0)
+    })
+}
+
+fn append_change(house_id: u64, change_type: &str, field: &str, old_value: String, new_value: String, current: &House) {
+    let sequence = next_sequence(house_id);
+    let snapshot = if sequence % CHECKPOINT_INTERVAL == 0 {
+        Some(current.clone())
+    } else {
+        None
+    };
+    let record = ChangeRecord {
+        sequence,
+        timestamp: time(),
+        change_type: change_type.to_string(),
+        field: field.to_string(),
+        old_value,
+        new_value,
+        snapshot,
+    };
+    CHANGE_LOG.with(|log| log.borrow_mut().insert(ChangeLogKey { house_id, sequence }, record));
+}
+
+fn log_field_changes(house_id: u64, change_type: &str, old: &House, new: &House) {
+    let mut changed_fields: Vec<(&str, String, String)> = Vec::new();
+    if old.owners_name != new.owners_name {
+        changed_fields.push(("owners_name", old.owners_name.clone(), new.owners_name.clone()));
+    }
+    if old.house_type != new.house_type {
+        changed_fields.push(("house_type", old.house_type.clone(), new.house_type.clone()));
+    }
+    if old.location != new.location {
+        changed_fields.push(("location", old.location.clone(), new.location.clone()));
+    }
+    if old.price != new.price {
+        changed_fields.push(("price", old.price.to_string(), new.price.to_string()));
+    }
+    if old.availabile_units != new.availabile_units {
+        changed_fields.push((
+            "availabile_units",
+            old.availabile_units.to_string(),
+            new.availabile_units.to_string(),
+        ));
+    }
+    if old.availability != new.availability {
+        changed_fields.push(("availability", old.availability.to_string(), new.availability.to_string()));
+    }
+
+    if changed_fields.is_empty() {
+        append_change(house_id, change_type, "none", String::new(), String::new(), new);
+    } else {
+        for (field, old_value, new_value) in changed_fields {
+            append_change(house_id, change_type, field, old_value, new_value, new);
+        }
+    }
+}
+
+fn apply_change_record(house: &mut House, record: &ChangeRecord) {
+    match record.field.as_str() {
+        "owners_name" => house.owners_name = record.new_value.clone(),
+        "house_type" => house.house_type = record.new_value.clone(),
+        "location" => house.location = record.new_value.clone(),
+        "price" => {
+            if let Ok(value) = record.new_value.parse() {
+                house.price = value;
+            }
+        }
+        "availabile_units" => {
+            if let Ok(value) = record.new_value.parse() {
+                house.availabile_units = value;
+            }
+        }
+        "availability" => {
+            if let Ok(value) = record.new_value.parse() {
+                house.availability = value;
+            }
+        }
+        _ => {}
+    }
+}
+
+#[ic_cdk::query]
+fn get_house_at(id: u64, sequence: u64) -> Result<House, Error> {
+    // Checkpoints land at deterministic sequence numbers, so the record to
+    // replay from can be looked up directly instead of scanning the whole
+    // history from 0, keeping replay cost bounded by CHECKPOINT_INTERVAL
+    // regardless of how long a house's history has grown.
+    let checkpoint_sequence = (sequence / CHECKPOINT_INTERVAL) * CHECKPOINT_INTERVAL;
+
+    let mut house = CHANGE_LOG
+        .with(|log| log.borrow().get(&ChangeLogKey { house_id: id, sequence: checkpoint_sequence }))
+        .and_then(|record| record.snapshot)
+        .ok_or_else(|| Error::NotFound {
+            msg: format!("no history for house with id={} at or before sequence={}", id, sequence),
+        })?;
+
+    let replay: Vec<ChangeRecord> = CHANGE_LOG.with(|log| {
+        log.borrow()
+            .range(
+                ChangeLogKey { house_id: id, sequence: checkpoint_sequence + 1 }
+                    ..=ChangeLogKey { house_id: id, sequence },
+            )
+            .map(|(_, record)| record)
+            .collect()
+    });
+    for record in &replay {
+        apply_change_record(&mut house, record);
+    }
+    Ok(house)
 }
 
 #[ic_cdk::query]
@@ -292,25 +938,96 @@ fn sort_house_by_name() -> Vec<House> {
     houses
 }
 
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct PageRequest {
+    after: Option<u64>,
+    limit: u64,
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct Page {
+    items: Vec<House>,
+    next_cursor: Option<u64>,
+    total: u64,
+}
+
+fn paginate_houses<F>(request: PageRequest, filter: F) -> Page
+where
+    F: Fn(&House) -> bool,
+{
+    STORAGE_HOUSE.with(|service| {
+        let service = service.borrow();
+        let total = service.iter().filter(|(_, house)| filter(house)).count() as u64;
+
+        let start = request.after.map(|id| id + 1).unwrap_or(0);
+        let items: Vec<House> = service
+            .range(start..)
+            .filter(|(_, house)| filter(house))
+            .take(request.limit as usize)
+            .map(|(_, house)| house.clone())
+            .collect();
+
+        let next_cursor = items.last().map(|house| house.id);
+        Page { items, next_cursor, total }
+    })
+}
+
+#[ic_cdk::query]
+fn get_all_houses_paginated(request: PageRequest) -> Page {
+    paginate_houses(request, |_| true)
+}
+
+#[ic_cdk::query]
+fn get_available_houses_paginated(request: PageRequest) -> Page {
+    paginate_houses(request, |house| house.availability)
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct NamePageRequest {
+    after: Option<NameIndexKey>,
+    limit: u64,
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct NamePage {
+    items: Vec<House>,
+    next_cursor: Option<NameIndexKey>,
+    total: u64,
+}
+
+// The cursor carries the last-seen (owners_name, house_id) directly, rather
+// than a house id to look back up, so a page still resumes at the right spot
+// in NAME_INDEX even if that house was since deleted.
+#[ic_cdk::query]
+fn sort_house_by_name_paginated(request: NamePageRequest) -> NamePage {
+    let total = STORAGE_HOUSE.with(|service| service.borrow().iter().count() as u64);
+
+    let start_bound = match request.after {
+        Some(cursor) => Bound::Excluded(cursor),
+        None => Bound::Unbounded,
+    };
+
+    let items: Vec<House> = NAME_INDEX.with(|index| {
+        index
+            .borrow()
+            .range((start_bound, Bound::Unbounded))
+            .take(request.limit as usize)
+            .filter_map(|(_, house_id)| _get_house(&house_id))
+            .collect()
+    });
+
+    let next_cursor = items.last().map(name_index_key);
+    NamePage { items, next_cursor, total }
+}
+
 #[ic_cdk::query]
 fn get_house_update_history(id: u64) -> Vec<ChangeRecord> {
-    match _get_house(&id) {
-        Some(house) => {
-            let mut history = Vec::new();
-            if let Some(updated_at) = house.updated_at {
-                history.push(ChangeRecord {
-                    timestamp: updated_at,
-                    change_type: "Update".to_string(),
-                });
-            }
-            history.push(ChangeRecord {
-                timestamp: house.created_at,
-                change_type: "Creation".to_string(),
-            });
-            history
-        }
-        None => Vec::new(),
-    }
+    CHANGE_LOG.with(|log| {
+        log.borrow()
+            .range(ChangeLogKey { house_id: id, sequence: 0 }..ChangeLogKey { house_id: id + 1, sequence: 0 })
+            .map(|(_, record)| record)
+            .collect()
+    })
 }
 
 ic_cdk::export_candid!();